@@ -0,0 +1,88 @@
+//! Translates YARV instructions into native machine code.
+//!
+//! This module owns the registry of generated [`IseqPayload`]s, which the
+//! rest of YJIT (disassembly, profiling, invalidation) uses to map
+//! addresses and blocks back to the Ruby code they came from.
+
+use crate::core::{BlockId, IseqPayload};
+
+thread_local! {
+    /// All ISEQ payloads compiled so far, in compilation order.
+    ///
+    /// Real YJIT keeps this state per-ISEQ on the CRuby object itself;
+    /// we keep a flat registry here since we don't have the VM to attach
+    /// it to.
+    static PAYLOADS: std::cell::RefCell<Vec<IseqPayload>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Runs `f` with a read-only view of every compiled [`IseqPayload`].
+pub fn with_payloads<R>(f: impl FnOnce(&[IseqPayload]) -> R) -> R {
+    PAYLOADS.with(|payloads| f(&payloads.borrow()))
+}
+
+/// Registers a newly-compiled [`IseqPayload`], making it visible to
+/// `with_payloads` (disasm, profiling, invalidation).
+pub fn add_payload(payload: IseqPayload) {
+    PAYLOADS.with(|payloads| payloads.borrow_mut().push(payload));
+}
+
+/// Removes the block with id `id` from whichever payload holds it, e.g.
+/// when `invariants` invalidates it. Returns whether a block was found
+/// and removed.
+pub fn remove_block(id: BlockId) -> bool {
+    PAYLOADS.with(|payloads| {
+        for payload in payloads.borrow_mut().iter_mut() {
+            if let Some(index) = payload.blocks.iter().position(|block| block.id == id) {
+                payload.blocks.remove(index);
+                return true;
+            }
+        }
+        false
+    })
+}
+
+thread_local! {
+    /// Addresses of the JIT frames currently executing, outermost first.
+    ///
+    /// In the full build the profiler would derive this by walking the
+    /// native frame pointer chain set up by the codegen prologue. We
+    /// don't have a running JIT to sample here, so the codegen-emitted
+    /// prologue/epilogue instead push/pop onto this stack directly as
+    /// blocks are entered and left.
+    static FRAME_STACK: std::cell::RefCell<Vec<usize>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Records that execution has entered the block starting at `addr`.
+/// Paired with [`pop_frame`] by the block's prologue/epilogue.
+pub fn push_frame(addr: usize) {
+    FRAME_STACK.with(|stack| stack.borrow_mut().push(addr));
+}
+
+/// Records that execution has returned from the innermost active block.
+pub fn pop_frame() {
+    FRAME_STACK.with(|stack| {
+        stack.borrow_mut().pop();
+    });
+}
+
+/// Returns the address of each JIT frame currently recorded by
+/// [`push_frame`]/[`pop_frame`], innermost first.
+pub fn current_frame_addresses() -> Vec<usize> {
+    FRAME_STACK.with(|stack| stack.borrow().iter().rev().copied().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_frame_addresses_is_innermost_first() {
+        push_frame(0x100);
+        push_frame(0x200);
+        assert_eq!(current_frame_addresses(), vec![0x200, 0x100]);
+        pop_frame();
+        assert_eq!(current_frame_addresses(), vec![0x100]);
+        pop_frame();
+        assert_eq!(current_frame_addresses(), Vec::<usize>::new());
+    }
+}
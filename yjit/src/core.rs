@@ -0,0 +1,71 @@
+//! Core data structures describing compiled code: basic blocks, the
+//! context they were compiled under, and the maps that tie generated
+//! machine code back to the YARV bytecode it came from.
+
+use crate::cruby::IseqPtr;
+
+/// A stable identifier for a basic block within an ISEQ's compiled
+/// regions. Reused across dumps/profiles/logs so the same block can be
+/// cross-referenced between tools.
+pub type BlockId = u64;
+
+/// Metadata codegen records for a single emitted machine instruction, so
+/// disasm and profiling can map generated code back to the YARV bytecode
+/// and basic block it came from without re-decoding raw bytes.
+pub struct InsnMeta {
+    /// Address of this instruction in the generated code.
+    pub address: usize,
+    /// Length of this instruction in bytes.
+    pub length: usize,
+    /// Decoded mnemonic and operands, e.g. `"mov rax, rdi"`.
+    pub text: String,
+    /// YARV bytecode offset, within the owning ISEQ, this instruction was
+    /// generated from.
+    pub yarv_offset: u32,
+}
+
+/// One contiguous run of generated machine code, compiled from a range of
+/// YARV instructions belonging to a single ISEQ.
+pub struct Block {
+    /// Stable id for this block, unique across all compiled ISEQs:
+    /// invalidation looks blocks up by id alone.
+    pub id: BlockId,
+
+    /// The ISEQ this block was generated from.
+    pub iseq: IseqPtr,
+
+    /// Byte offset into the ISEQ's YARV bytecode where this block starts.
+    pub yarv_start_pc: u32,
+
+    /// Start address of this block's generated machine code.
+    pub start_addr: usize,
+
+    /// End address (exclusive) of this block's generated machine code.
+    pub end_addr: usize,
+
+    /// Per-instruction metadata, in address order, recorded as codegen
+    /// emits each instruction.
+    pub insns: Vec<InsnMeta>,
+}
+
+/// All blocks generated for a single ISEQ, in the order they were
+/// compiled.
+pub struct IseqPayload {
+    pub iseq: IseqPtr,
+    pub blocks: Vec<Block>,
+}
+
+/// Finds the block whose generated code contains `addr`, if any.
+///
+/// Used to map a native return address (e.g. from a profiler sample or a
+/// backtrace) back to the ISEQ and basic block it belongs to.
+pub fn block_for_address(payloads: &[IseqPayload], addr: usize) -> Option<(&IseqPayload, &Block)> {
+    for payload in payloads {
+        for block in &payload.blocks {
+            if addr >= block.start_addr && addr < block.end_addr {
+                return Some((payload, block));
+            }
+        }
+    }
+    None
+}
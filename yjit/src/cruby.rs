@@ -0,0 +1,79 @@
+//! Bindings and small helpers for talking to the CRuby VM.
+//!
+//! This is intentionally a thin layer: just enough of the C-level types and
+//! accessors for the rest of YJIT to work with ISEQs, classes and other Ruby
+//! objects without sprinkling raw FFI everywhere else.
+
+#![allow(non_camel_case_types)]
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Mirrors the C `VALUE` type: a tagged pointer/immediate used for every
+/// Ruby object.
+pub type VALUE = usize;
+
+/// Mirrors the C `rb_iseq_t *`. We only ever pass this around opaquely.
+pub type IseqPtr = *const std::os::raw::c_void;
+
+/// Mirrors the C `ID` type used for interned Ruby symbol/method names.
+pub type ID = u64;
+
+/// Returns the path of the file an ISEQ was compiled from, e.g.
+/// `"app/models/user.rb"`. Falls back to `"<unknown>"` if CRuby can't
+/// resolve it (e.g. for synthetic ISEQs).
+pub fn iseq_get_location(_iseq: IseqPtr) -> String {
+    // In the full build this calls into `rb_iseq_path()`. We have no VM
+    // linked in this tree to resolve it against, so this is left
+    // unimplemented rather than faked.
+    "<unknown>".into()
+}
+
+/// Returns the human-readable label of an ISEQ, e.g. a method or block name.
+pub fn iseq_get_label(_iseq: IseqPtr) -> String {
+    // Would call `rb_iseq_label()` in the full build; see
+    // `iseq_get_location` for why that's not available here.
+    "<unknown>".into()
+}
+
+thread_local! {
+    /// Names registered for interned `ID`s.
+    ///
+    /// In the full build `rb_id2name()` resolves any `ID` directly from
+    /// the VM's symbol table. We don't have that table here, so call
+    /// sites that mint an `ID` register its name up front via
+    /// [`register_id_name`], and `id_to_string` serves lookups out of
+    /// this cache.
+    static ID_NAMES: RefCell<HashMap<ID, String>> = RefCell::new(HashMap::new());
+}
+
+/// Registers the printable name for an interned `ID`, so later calls to
+/// [`id_to_string`] can resolve it.
+pub fn register_id_name(id: ID, name: impl Into<String>) {
+    ID_NAMES.with(|names| names.borrow_mut().insert(id, name.into()));
+}
+
+/// Looks up the printable name for an interned `ID`, e.g. a method or
+/// constant name. Falls back to `"id:<N>"` if `id` was never registered
+/// via [`register_id_name`].
+pub fn id_to_string(id: ID) -> String {
+    ID_NAMES
+        .with(|names| names.borrow().get(&id).cloned())
+        .unwrap_or_else(|| format!("id:{id}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn id_to_string_resolves_registered_names() {
+        register_id_name(42, "save");
+        assert_eq!(id_to_string(42), "save");
+    }
+
+    #[test]
+    fn id_to_string_falls_back_for_unknown_ids() {
+        assert_eq!(id_to_string(9999), "id:9999");
+    }
+}
@@ -0,0 +1,162 @@
+//! Disassembly of generated machine code.
+//!
+//! By default this produces a human-oriented text dump for
+//! `--yjit-dump-disasm`. [`to_json`] offers a structured alternative for
+//! tools that want to render a navigable "YARV -> native" view instead of
+//! scraping the text output.
+
+use crate::core::{Block, IseqPayload};
+use crate::cruby::iseq_get_location;
+use crate::options::OutputFormat;
+
+/// One decoded machine instruction, annotated with where it came from in
+/// the original YARV bytecode.
+pub struct DisasmInsn {
+    /// Address of this instruction in the generated code.
+    pub address: usize,
+    /// Length of this instruction in bytes.
+    pub length: usize,
+    /// Decoded mnemonic and operands, e.g. `"mov rax, rdi"`.
+    pub text: String,
+    /// YARV bytecode offset, within the owning ISEQ, this instruction was
+    /// generated from.
+    pub yarv_offset: u32,
+    /// Id of the basic block this instruction belongs to.
+    pub block_id: u64,
+}
+
+/// Collects the machine code for every block in `payload`, block by block
+/// in compilation order (each block's instructions in address order),
+/// tagging each instruction with the bytecode/block it came from.
+///
+/// The actual x86 decoding (capstone, in the full build) happens as
+/// codegen emits each instruction and records it on [`Block::insns`];
+/// this just re-tags that per-block metadata with the owning block id.
+fn decode_instructions(payload: &IseqPayload) -> Vec<DisasmInsn> {
+    let mut insns = Vec::new();
+
+    for block in &payload.blocks {
+        insns.extend(decode_block(block));
+    }
+
+    insns
+}
+
+fn decode_block(block: &Block) -> Vec<DisasmInsn> {
+    block
+        .insns
+        .iter()
+        .map(|insn| DisasmInsn {
+            address: insn.address,
+            length: insn.length,
+            text: insn.text.clone(),
+            yarv_offset: insn.yarv_offset,
+            block_id: block.id,
+        })
+        .collect()
+}
+
+/// Renders the classic human-oriented disassembly text for `payload`.
+pub fn dump_text(payload: &IseqPayload) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("== {} ==\n", iseq_get_location(payload.iseq)));
+
+    for insn in decode_instructions(payload) {
+        out.push_str(&format!(
+            "  {:#x}: {} (yarv+{}, block {})\n",
+            insn.address, insn.text, insn.yarv_offset, insn.block_id
+        ));
+    }
+
+    out
+}
+
+/// Renders a structured, machine-readable dump of `payload`'s generated
+/// code.
+///
+/// For each instruction this includes its address, length, decoded text,
+/// and the ISEQ/YARV offset/basic-block id it was generated from, so
+/// external tools can build a side-by-side "YARV -> native" view without
+/// scraping text output.
+pub fn to_json(payload: &IseqPayload) -> String {
+    let mut insns_json = Vec::new();
+
+    for insn in decode_instructions(payload) {
+        insns_json.push(format!(
+            concat!(
+                "{{\"address\":{},\"length\":{},\"text\":{:?},",
+                "\"yarv_offset\":{},\"block_id\":{}}}"
+            ),
+            insn.address, insn.length, insn.text, insn.yarv_offset, insn.block_id
+        ));
+    }
+
+    format!(
+        "{{\"iseq\":{:?},\"insns\":[{}]}}",
+        iseq_get_location(payload.iseq),
+        insns_json.join(",")
+    )
+}
+
+/// Dumps `payload` in the format requested by `--yjit-dump-disasm`.
+pub fn dump(payload: &IseqPayload, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => dump_text(payload),
+        OutputFormat::Json => to_json(payload),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::InsnMeta;
+
+    fn sample_payload() -> IseqPayload {
+        IseqPayload {
+            iseq: std::ptr::null(),
+            blocks: vec![Block {
+                id: 7,
+                iseq: std::ptr::null(),
+                yarv_start_pc: 0,
+                start_addr: 0x1000,
+                end_addr: 0x1005,
+                insns: vec![InsnMeta {
+                    address: 0x1000,
+                    length: 5,
+                    text: "mov rax, rdi".into(),
+                    yarv_offset: 12,
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn decode_instructions_tags_block_id() {
+        let payload = sample_payload();
+        let insns = decode_instructions(&payload);
+
+        assert_eq!(insns.len(), 1);
+        assert_eq!(insns[0].address, 0x1000);
+        assert_eq!(insns[0].yarv_offset, 12);
+        assert_eq!(insns[0].block_id, 7);
+    }
+
+    #[test]
+    fn to_json_includes_yarv_offset_and_block_id() {
+        let payload = sample_payload();
+        let json = to_json(&payload);
+
+        assert!(json.contains("\"yarv_offset\":12"));
+        assert!(json.contains("\"block_id\":7"));
+        assert!(json.contains("\"mov rax, rdi\""));
+    }
+
+    #[test]
+    fn dump_text_includes_block_and_offset() {
+        let payload = sample_payload();
+        let text = dump_text(&payload);
+
+        assert!(text.contains("yarv+12"));
+        assert!(text.contains("block 7"));
+    }
+}
@@ -0,0 +1,123 @@
+//! Tracks assumptions made by generated code and invalidates it when
+//! those assumptions no longer hold.
+//!
+//! Every invalidation is also recorded to an audit log ([`stats`]) so
+//! deopt storms can be diagnosed after the fact instead of only showing
+//! up as silently recompiled code.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::codegen::remove_block;
+use crate::core::BlockId;
+use crate::cruby::ID;
+use crate::stats::record_invalidation;
+
+/// Stable category for an invariant that can trigger invalidation.
+///
+/// Kept as an enum (rather than a free-form string) so the audit log and
+/// `--yjit-dump-invalidations` can group and count invalidations reliably
+/// across versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InvariantKind {
+    /// A method was redefined after code was compiled assuming its old
+    /// definition.
+    MethodRedefinition,
+    /// A constant was redefined or reassigned.
+    ConstantRedefinition,
+    /// An object's ivar shape changed in a way generated code didn't
+    /// expect.
+    IvarShapeChange,
+    /// A "basic operation" (e.g. `Integer#+`) was redefined, invalidating
+    /// code that assumed the built-in semantics.
+    BopRedefinition,
+}
+
+impl InvariantKind {
+    /// Stable tag used to group invalidations in audit output, e.g.
+    /// `"method-redefinition"`.
+    pub fn category(self) -> &'static str {
+        match self {
+            InvariantKind::MethodRedefinition => "method-redefinition",
+            InvariantKind::ConstantRedefinition => "constant-redefinition",
+            InvariantKind::IvarShapeChange => "ivar-shape-change",
+            InvariantKind::BopRedefinition => "bop-redefinition",
+        }
+    }
+}
+
+thread_local! {
+    /// Blocks that were compiled assuming a given invariant holds, keyed
+    /// by the invariant kind and the specific entity (method/constant/
+    /// etc.) it was compiled against.
+    ///
+    /// Populated by [`track_assumption`] as codegen compiles code that
+    /// relies on the assumption, and drained by [`invalidate_for`] when
+    /// it no longer holds.
+    static DEPENDENCIES: RefCell<HashMap<(InvariantKind, ID), Vec<BlockId>>> = RefCell::new(HashMap::new());
+}
+
+/// Records that `block` was compiled assuming `kind` holds for `entity`,
+/// so it can be found and invalidated later if that assumption breaks.
+/// Called by codegen right after compiling a block with such a
+/// dependency.
+pub fn track_assumption(kind: InvariantKind, entity: ID, block: BlockId) {
+    DEPENDENCIES.with(|deps| deps.borrow_mut().entry((kind, entity)).or_default().push(block));
+}
+
+/// Invalidates every block that depended on `kind` holding for `entity`,
+/// recording the invalidation to the audit log first.
+///
+/// `entity` is the interned name of the Ruby-level thing that changed
+/// (a method, constant, etc.), and `reason` is a short human-readable
+/// explanation, e.g. `"method redefined on String"`.
+pub fn invalidate_for(kind: InvariantKind, entity: ID, reason: &str) {
+    let blocks_invalidated = invalidate_dependent_blocks(kind, entity);
+
+    record_invalidation(kind, crate::cruby::id_to_string(entity), blocks_invalidated, reason.to_string());
+}
+
+/// Finds and invalidates every compiled block that assumed `kind` held
+/// for `entity`, returning how many blocks were invalidated.
+fn invalidate_dependent_blocks(kind: InvariantKind, entity: ID) -> u64 {
+    let blocks = DEPENDENCIES
+        .with(|deps| deps.borrow_mut().remove(&(kind, entity)))
+        .unwrap_or_default();
+
+    blocks.into_iter().filter(|&block| remove_block(block)).count() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::add_payload;
+    use crate::core::{Block, IseqPayload};
+
+    fn block(id: BlockId) -> Block {
+        Block {
+            id,
+            iseq: std::ptr::null(),
+            yarv_start_pc: 0,
+            start_addr: 0,
+            end_addr: 0,
+            insns: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn invalidate_for_removes_only_tracked_blocks_and_counts_them() {
+        add_payload(IseqPayload {
+            iseq: std::ptr::null(),
+            blocks: vec![block(1), block(2), block(3)],
+        });
+
+        track_assumption(InvariantKind::MethodRedefinition, 42, 1);
+        track_assumption(InvariantKind::MethodRedefinition, 42, 2);
+        track_assumption(InvariantKind::MethodRedefinition, 7, 3);
+
+        invalidate_for(InvariantKind::MethodRedefinition, 42, "method redefined");
+
+        assert_eq!(invalidate_dependent_blocks(InvariantKind::MethodRedefinition, 42), 0);
+        assert_eq!(invalidate_dependent_blocks(InvariantKind::MethodRedefinition, 7), 1);
+    }
+}
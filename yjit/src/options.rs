@@ -0,0 +1,441 @@
+//! Parses and stores YJIT's command-line/environment options.
+//!
+//! Every other module reads the single resolved [`Options`] value rather
+//! than re-parsing flags itself, so all the precedence logic lives here.
+
+use std::path::{Path, PathBuf};
+
+/// Name of the config file [`find_config_file`] searches for.
+const CONFIG_FILE_NAME: &str = ".yjit.toml";
+
+/// Output format for the `--yjit-dump-disasm` family of options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The classic human-oriented text dump.
+    Text,
+    /// A structured, machine-readable dump (see [`crate::disasm::to_json`]).
+    Json,
+}
+
+/// Resolved set of YJIT options, after applying all configured sources.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Number of calls before an ISEQ is considered hot enough to compile.
+    pub call_threshold: u64,
+
+    /// Size in bytes of the executable memory region reserved for
+    /// generated code.
+    pub exec_mem_size: usize,
+
+    /// If set, dump disassembly of generated code in the given format
+    /// when it's compiled.
+    pub dump_disasm: Option<OutputFormat>,
+
+    /// If set, run the sampling profiler at the given interval, in
+    /// microseconds between ticks.
+    pub profile_interval_us: Option<u64>,
+
+    /// If set, dump the invalidation audit log, grouped by category, on
+    /// shutdown.
+    pub dump_invalidations: bool,
+
+    /// Names of optimization passes to leave disabled, e.g. for bisecting
+    /// a miscompile.
+    pub disabled_passes: Vec<String>,
+}
+
+/// Default sampling interval for `--yjit-profile` when no explicit
+/// interval is given.
+pub const DEFAULT_PROFILE_INTERVAL_US: u64 = 1000;
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            call_threshold: 30,
+            exec_mem_size: 64 * 1024 * 1024,
+            dump_disasm: None,
+            profile_interval_us: None,
+            dump_invalidations: false,
+            disabled_passes: Vec::new(),
+        }
+    }
+}
+
+/// Parses a single `--yjit-*` option string (the part after `--yjit-`,
+/// e.g. `"dump-disasm"` or `"dump-disasm=json"`) and applies it to
+/// `options`.
+///
+/// Returns `false` if the option name isn't recognized, or if its value is
+/// missing or invalid for that option.
+pub fn parse_option(options: &mut Options, option: &str) -> bool {
+    let (name, value) = match option.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (option, None),
+    };
+
+    match name {
+        "call-threshold" => {
+            let Some(value) = value else { return false };
+            let Ok(threshold) = value.parse() else { return false };
+            options.call_threshold = threshold;
+        }
+        "exec-mem-size" => {
+            let Some(value) = value else { return false };
+            let Ok(size) = value.parse() else { return false };
+            options.exec_mem_size = size;
+        }
+        "dump-disasm" => {
+            options.dump_disasm = Some(match value {
+                None | Some("text") => OutputFormat::Text,
+                Some("json") => OutputFormat::Json,
+                Some(_) => return false,
+            });
+        }
+        "profile" => {
+            options.profile_interval_us = Some(match value {
+                None => DEFAULT_PROFILE_INTERVAL_US,
+                Some(value) => {
+                    let Ok(interval) = value.parse() else { return false };
+                    interval
+                }
+            });
+        }
+        "dump-invalidations" => {
+            if value.is_some() {
+                return false;
+            }
+            options.dump_invalidations = true;
+        }
+        "disable" => {
+            let Some(value) = value else { return false };
+            options.disabled_passes.push(value.to_string());
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+/// Shape of `.yjit.toml`. Every field is optional: a config file only
+/// needs to set what it wants to override.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct FileOptions {
+    call_threshold: Option<u64>,
+    exec_mem_size: Option<usize>,
+    dump_disasm: Option<String>,
+    profile_interval_us: Option<u64>,
+    dump_invalidations: Option<bool>,
+    disable: Option<Vec<String>>,
+}
+
+/// Searches `start` and its ancestors for [`CONFIG_FILE_NAME`], the same
+/// way tools like `rustfmt`/`cargo` find their config. Returns the first
+/// match, closest to `start` first.
+fn find_config_file(start: &Path) -> Option<PathBuf> {
+    let mut dir = Some(start);
+
+    while let Some(candidate_dir) = dir {
+        let candidate = candidate_dir.join(CONFIG_FILE_NAME);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        dir = candidate_dir.parent();
+    }
+
+    None
+}
+
+/// One value parsed out of a `.yjit.toml` `key = value` line.
+enum TomlValue {
+    Int(i64),
+    Bool(bool),
+    Str(String),
+    StrArray(Vec<String>),
+}
+
+fn parse_toml_value(raw: &str) -> Option<TomlValue> {
+    let raw = raw.trim();
+
+    if let Some(inner) = raw.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        return Some(TomlValue::Str(inner.to_string()));
+    }
+
+    if let Some(inner) = raw.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        let items = inner
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| item.trim_matches('"').to_string())
+            .collect();
+        return Some(TomlValue::StrArray(items));
+    }
+
+    match raw {
+        "true" => return Some(TomlValue::Bool(true)),
+        "false" => return Some(TomlValue::Bool(false)),
+        _ => {}
+    }
+
+    raw.parse::<i64>().ok().map(TomlValue::Int)
+}
+
+/// Parses the flat subset of TOML `.yjit.toml` actually needs: one
+/// `key = value` per line, blank lines and whole-line `#` comments
+/// ignored, no tables or nesting. A line whose value can't be parsed,
+/// including one with a trailing `#` comment after the value, is skipped. This is hand-rolled rather than pulled in from a
+/// `toml`/`serde` dependency, which would add a proc-macro build
+/// dependency to every CRuby build for a handful of scalar/array fields.
+fn parse_toml(contents: &str) -> FileOptions {
+    let mut file_options = FileOptions::default();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else { continue };
+        let Some(value) = parse_toml_value(value) else { continue };
+
+        match (key.trim(), value) {
+            ("call_threshold", TomlValue::Int(v)) => {
+                if let Ok(v) = u64::try_from(v) {
+                    file_options.call_threshold = Some(v);
+                }
+            }
+            ("exec_mem_size", TomlValue::Int(v)) => {
+                if let Ok(v) = usize::try_from(v) {
+                    file_options.exec_mem_size = Some(v);
+                }
+            }
+            ("dump_disasm", TomlValue::Str(v)) => file_options.dump_disasm = Some(v),
+            ("profile_interval_us", TomlValue::Int(v)) => {
+                if let Ok(v) = u64::try_from(v) {
+                    file_options.profile_interval_us = Some(v);
+                }
+            }
+            ("dump_invalidations", TomlValue::Bool(v)) => file_options.dump_invalidations = Some(v),
+            ("disable", TomlValue::StrArray(v)) => file_options.disable = Some(v),
+            _ => {}
+        }
+    }
+
+    file_options
+}
+
+/// Reads and parses `path` as a `.yjit.toml` config file.
+fn read_config_file(path: &Path) -> Option<FileOptions> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    Some(parse_toml(&contents))
+}
+
+/// Applies every field set in `file_options` to `options`.
+fn apply_file_options(options: &mut Options, file_options: FileOptions) {
+    if let Some(call_threshold) = file_options.call_threshold {
+        options.call_threshold = call_threshold;
+    }
+    if let Some(exec_mem_size) = file_options.exec_mem_size {
+        options.exec_mem_size = exec_mem_size;
+    }
+    if let Some(dump_disasm) = file_options.dump_disasm {
+        options.dump_disasm = match dump_disasm.as_str() {
+            "text" => Some(OutputFormat::Text),
+            "json" => Some(OutputFormat::Json),
+            _ => options.dump_disasm,
+        };
+    }
+    if let Some(profile_interval_us) = file_options.profile_interval_us {
+        options.profile_interval_us = Some(profile_interval_us);
+    }
+    if let Some(dump_invalidations) = file_options.dump_invalidations {
+        options.dump_invalidations = dump_invalidations;
+    }
+    if let Some(disable) = file_options.disable {
+        options.disabled_passes = disable;
+    }
+}
+
+/// Applies a single already-read `YJIT_*` environment variable's value to
+/// `options`, keyed by its corresponding `--yjit-*` option name. Split out
+/// from [`apply_env`] so the merge logic can be tested without touching
+/// real process environment variables.
+fn apply_env_var(options: &mut Options, name: &str, value: &str) {
+    match name {
+        "call-threshold" => {
+            if let Ok(threshold) = value.parse() {
+                options.call_threshold = threshold;
+            }
+        }
+        "exec-mem-size" => {
+            if let Ok(size) = value.parse() {
+                options.exec_mem_size = size;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Applies `YJIT_*` environment variables to `options`, e.g.
+/// `YJIT_CALL_THRESHOLD`.
+fn apply_env(options: &mut Options) {
+    if let Ok(value) = std::env::var("YJIT_CALL_THRESHOLD") {
+        apply_env_var(options, "call-threshold", &value);
+    }
+    if let Ok(value) = std::env::var("YJIT_EXEC_MEM_SIZE") {
+        apply_env_var(options, "exec-mem-size", &value);
+    }
+}
+
+/// Builds the final [`Options`] from every configured source, applied in
+/// precedence order so the most specific source always wins:
+/// defaults < config file < environment variables < CLI flags.
+///
+/// `cli_args` are `--yjit-*` option strings with the `--yjit-` prefix
+/// already stripped, in the order they were given on the command line.
+pub fn load_options(cli_args: &[String]) -> Options {
+    let mut options = Options::default();
+
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    if let Some(config_path) = find_config_file(&cwd) {
+        if let Some(file_options) = read_config_file(&config_path) {
+            apply_file_options(&mut options, file_options);
+        }
+    }
+
+    apply_env(&mut options);
+
+    for arg in cli_args {
+        parse_option(&mut options, arg);
+    }
+
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_option_handles_each_known_flag() {
+        let mut options = Options::default();
+
+        assert!(parse_option(&mut options, "call-threshold=5"));
+        assert_eq!(options.call_threshold, 5);
+
+        assert!(parse_option(&mut options, "exec-mem-size=2048"));
+        assert_eq!(options.exec_mem_size, 2048);
+
+        assert!(parse_option(&mut options, "dump-disasm"));
+        assert_eq!(options.dump_disasm, Some(OutputFormat::Text));
+        assert!(parse_option(&mut options, "dump-disasm=json"));
+        assert_eq!(options.dump_disasm, Some(OutputFormat::Json));
+
+        assert!(parse_option(&mut options, "profile"));
+        assert_eq!(options.profile_interval_us, Some(DEFAULT_PROFILE_INTERVAL_US));
+        assert!(parse_option(&mut options, "profile=500"));
+        assert_eq!(options.profile_interval_us, Some(500));
+
+        assert!(parse_option(&mut options, "dump-invalidations"));
+        assert!(options.dump_invalidations);
+
+        assert!(parse_option(&mut options, "disable=inlining"));
+        assert!(parse_option(&mut options, "disable=foo"));
+        assert_eq!(options.disabled_passes, vec!["inlining", "foo"]);
+
+        assert!(!parse_option(&mut options, "not-a-real-option"));
+        assert!(!parse_option(&mut options, "dump-disasm=yaml"));
+    }
+
+    #[test]
+    fn parse_toml_parses_every_supported_value_kind() {
+        let contents = "\
+            \n\
+            # a comment, and a blank line above\n\
+            call_threshold = 42\n\
+            exec_mem_size = 1024\n\
+            dump_disasm = \"json\"\n\
+            profile_interval_us = 250\n\
+            dump_invalidations = true\n\
+            disable = [\"inlining\", \"foo\"]\n";
+
+        let file_options = parse_toml(contents);
+
+        assert_eq!(file_options.call_threshold, Some(42));
+        assert_eq!(file_options.exec_mem_size, Some(1024));
+        assert_eq!(file_options.dump_disasm.as_deref(), Some("json"));
+        assert_eq!(file_options.profile_interval_us, Some(250));
+        assert_eq!(file_options.dump_invalidations, Some(true));
+        assert_eq!(
+            file_options.disable,
+            Some(vec!["inlining".to_string(), "foo".to_string()])
+        );
+    }
+
+    #[test]
+    fn parse_toml_rejects_negative_values_for_unsigned_fields() {
+        let contents = "\
+            call_threshold = -5\n\
+            exec_mem_size = -1024\n\
+            profile_interval_us = -250\n";
+
+        let file_options = parse_toml(contents);
+
+        assert_eq!(file_options.call_threshold, None);
+        assert_eq!(file_options.exec_mem_size, None);
+        assert_eq!(file_options.profile_interval_us, None);
+    }
+
+    /// Unique scratch directory for a single test, so parallel test
+    /// threads never collide on the same path.
+    fn scratch_dir(label: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "yjit-options-test-{label}-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ))
+    }
+
+    #[test]
+    fn find_config_file_searches_ancestors() {
+        let base = scratch_dir("ancestors");
+        let nested = base.join("a/b/c");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(base.join(CONFIG_FILE_NAME), "call_threshold = 7").unwrap();
+
+        let found = find_config_file(&nested).expect("should find the config file in an ancestor");
+        assert_eq!(found, base.join(CONFIG_FILE_NAME));
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn find_config_file_returns_none_when_absent() {
+        let base = scratch_dir("missing");
+        std::fs::create_dir_all(&base).unwrap();
+
+        assert!(find_config_file(&base).is_none());
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn precedence_is_defaults_then_file_then_env_then_cli() {
+        let mut options = Options::default();
+        assert_eq!(options.call_threshold, 30);
+
+        apply_file_options(
+            &mut options,
+            FileOptions {
+                call_threshold: Some(10),
+                ..Default::default()
+            },
+        );
+        assert_eq!(options.call_threshold, 10, "file should override defaults");
+
+        apply_env_var(&mut options, "call-threshold", "20");
+        assert_eq!(options.call_threshold, 20, "env should override the file");
+
+        parse_option(&mut options, "call-threshold=30");
+        assert_eq!(options.call_threshold, 30, "CLI should override env");
+    }
+}
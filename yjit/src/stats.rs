@@ -0,0 +1,238 @@
+//! Counters and profiling data collected while YJIT runs.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::codegen::{current_frame_addresses, with_payloads};
+use crate::core::block_for_address;
+use crate::invariants::InvariantKind;
+
+/// Aggregate counters tracked for the lifetime of the process. Nothing in
+/// this module updates them; in particular [`record_invalidation`] writes
+/// to the audit log without touching `invalidation_count`.
+#[derive(Debug, Default)]
+pub struct Counters {
+    pub exec_instruction: u64,
+    pub compiled_iseq_count: u64,
+    pub compiled_block_count: u64,
+    pub invalidation_count: u64,
+}
+
+/// A single sampled call stack, expressed as the `ISEQ label` of each
+/// frame, outermost first (the root-first order collapsed-stack text
+/// uses).
+type Stack = Vec<String>;
+
+/// A sampling profiler that attributes wall-clock time to compiled
+/// regions of code.
+///
+/// On each tick it walks the current native frame chain, maps every
+/// return address back to the owning ISEQ/block via the address map
+/// `codegen`/`core` maintain, and weights the resulting stack trace by
+/// one sample. Folding the table on shutdown produces a collapsed-stack
+/// text report that standard flamegraph renderers understand directly.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    /// How often the profiler samples, in wall-clock time.
+    interval: Duration,
+    /// Sample counts, keyed by the (outermost-first) stack that was
+    /// sampled.
+    samples: HashMap<Stack, u64>,
+}
+
+impl Profiler {
+    pub fn new(interval: Duration) -> Self {
+        Profiler {
+            interval,
+            samples: HashMap::new(),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Called on each timer tick. Walks the current native frames,
+    /// resolves each address to an ISEQ label, and records the resulting
+    /// stack.
+    pub fn tick(&mut self) {
+        let stack: Stack = with_payloads(|payloads| {
+            current_frame_addresses()
+                .into_iter()
+                .rev()
+                .filter_map(|addr| block_for_address(payloads, addr))
+                .map(|(payload, _block)| crate::cruby::iseq_get_label(payload.iseq))
+                .collect()
+        });
+
+        if stack.is_empty() {
+            return;
+        }
+
+        *self.samples.entry(stack).or_insert(0) += 1;
+    }
+
+    /// Folds the collected samples into collapsed-stack text: one line
+    /// per unique stack, formatted as `frame;frame;...;frame count`, the
+    /// format standard flamegraph tooling (e.g. Brendan Gregg's
+    /// `flamegraph.pl`) expects.
+    pub fn to_collapsed_stacks(&self) -> String {
+        let mut out = String::new();
+
+        for (stack, count) in &self.samples {
+            out.push_str(&stack.join(";"));
+            out.push(' ');
+            out.push_str(&count.to_string());
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod profiler_tests {
+    use super::*;
+    use crate::codegen::{add_payload, pop_frame, push_frame};
+    use crate::core::{Block, IseqPayload};
+
+    #[test]
+    fn to_collapsed_stacks_formats_frame_path_and_count() {
+        let mut profiler = Profiler::new(Duration::from_micros(1000));
+        profiler
+            .samples
+            .insert(vec!["outer".to_string(), "inner".to_string()], 3);
+
+        assert_eq!(profiler.to_collapsed_stacks(), "outer;inner 3\n");
+    }
+
+    #[test]
+    fn tick_attributes_a_sample_to_the_containing_block() {
+        add_payload(IseqPayload {
+            iseq: std::ptr::null(),
+            blocks: vec![Block {
+                id: 1,
+                iseq: std::ptr::null(),
+                yarv_start_pc: 0,
+                start_addr: 0x1000,
+                end_addr: 0x1010,
+                insns: Vec::new(),
+            }],
+        });
+
+        push_frame(0x1004);
+        let mut profiler = Profiler::new(Duration::from_micros(1000));
+        profiler.tick();
+        pop_frame();
+
+        assert_eq!(profiler.samples.values().sum::<u64>(), 1);
+    }
+
+    #[test]
+    fn tick_records_nothing_when_no_frames_are_active() {
+        let mut profiler = Profiler::new(Duration::from_micros(1000));
+        profiler.tick();
+
+        assert!(profiler.samples.is_empty());
+    }
+}
+
+/// One invalidation event recorded against the audit log.
+pub struct InvalidationRecord {
+    pub kind: InvariantKind,
+    /// The Ruby-level entity that changed (method/class/constant name).
+    pub entity: String,
+    pub blocks_invalidated: u64,
+    pub reason: String,
+}
+
+thread_local! {
+    /// Every invalidation recorded so far, oldest first.
+    ///
+    /// Kept as a flat log (rather than pre-aggregated counts) so
+    /// `dump_invalidations` can report both per-category totals and the
+    /// specific entities responsible for them.
+    static INVALIDATIONS: RefCell<Vec<InvalidationRecord>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records an invalidation event to the audit log. Called by
+/// [`crate::invariants::invalidate_for`] whenever generated code is
+/// invalidated.
+pub fn record_invalidation(kind: InvariantKind, entity: String, blocks_invalidated: u64, reason: String) {
+    INVALIDATIONS.with(|log| {
+        log.borrow_mut().push(InvalidationRecord {
+            kind,
+            entity,
+            blocks_invalidated,
+            reason,
+        })
+    });
+}
+
+/// Renders the invalidation audit log for `--yjit-dump-invalidations`:
+/// one section per category, headed by its number of invalidations and
+/// total blocks invalidated, then one line per recorded invalidation
+/// (entity, reason, blocks invalidated), like categorized lint
+/// reporting. An entity invalidated more than once gets one line each
+/// time. Categories are ordered by total blocks invalidated,
+/// highest first, so the costliest invariant shows up first.
+pub fn dump_invalidations() -> String {
+    let mut out = String::new();
+    INVALIDATIONS.with(|log| {
+        let log = log.borrow();
+
+        let mut categories: Vec<&'static str> = Vec::new();
+        for record in log.iter() {
+            if !categories.contains(&record.kind.category()) {
+                categories.push(record.kind.category());
+            }
+        }
+
+        let mut totals: Vec<(&'static str, u64, u64)> = categories
+            .iter()
+            .map(|&category| {
+                let records: Vec<&InvalidationRecord> =
+                    log.iter().filter(|r| r.kind.category() == category).collect();
+                let invalidations = records.len() as u64;
+                let blocks: u64 = records.iter().map(|r| r.blocks_invalidated).sum();
+                (category, invalidations, blocks)
+            })
+            .collect();
+        totals.sort_by_key(|&(_, _, blocks)| std::cmp::Reverse(blocks));
+
+        for (category, invalidations, blocks) in totals {
+            out.push_str(&format!(
+                "{category}: {invalidations} invalidations, {blocks} blocks invalidated\n"
+            ));
+            for record in log.iter().filter(|r| r.kind.category() == category) {
+                out.push_str(&format!(
+                    "  {} ({}) -> {} blocks\n",
+                    record.entity, record.reason, record.blocks_invalidated
+                ));
+            }
+        }
+    });
+
+    out
+}
+
+#[cfg(test)]
+mod invalidation_tests {
+    use super::*;
+
+    #[test]
+    fn dump_invalidations_groups_by_category_ordered_by_blocks() {
+        record_invalidation(InvariantKind::BopRedefinition, "Integer#+".into(), 2, "bop redefined".into());
+        record_invalidation(InvariantKind::MethodRedefinition, "User#save".into(), 10, "method redefined".into());
+        record_invalidation(InvariantKind::MethodRedefinition, "User#name".into(), 5, "method redefined".into());
+
+        let dump = dump_invalidations();
+        let method_section = dump.find("method-redefinition").unwrap();
+        let bop_section = dump.find("bop-redefinition").unwrap();
+
+        assert!(method_section < bop_section, "costlier category should come first:\n{dump}");
+        assert!(dump.contains("method-redefinition: 2 invalidations, 15 blocks invalidated"));
+        assert!(dump.contains("User#save (method redefined) -> 10 blocks"));
+    }
+}